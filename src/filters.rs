@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::UNITS;
+
+/// Scoping predicates applied while walking: extension allowlist, exclude
+/// globs, and a minimum size floor. Exclude globs are checked against whole
+/// directories too, via `allows_path`, so excluded trees like `.git` or
+/// `target` are never descended into in the first place.
+pub struct ScanFilters {
+    extensions: Option<HashSet<String>>,
+    exclude: Option<GlobSet>,
+    min_size: u64,
+}
+
+impl ScanFilters {
+    pub fn new(extensions: Option<&str>, exclude: &[String], min_size: Option<&str>) -> Result<ScanFilters, String> {
+        let extensions = extensions.map(|raw| {
+            raw.split(',')
+                .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+                .collect()
+        });
+
+        let exclude = if exclude.is_empty() {
+            None
+        } else {
+            let mut builder = GlobSetBuilder::new();
+            for pattern in exclude {
+                let glob = Glob::new(pattern).map_err(|err| format!("Invalid --exclude pattern '{}': {}", pattern, err))?;
+                builder.add(glob);
+            }
+            Some(builder.build().map_err(|err| format!("Failed to build exclude glob set: {}", err))?)
+        };
+
+        let min_size = match min_size {
+            Some(raw) => parse_size(raw)?,
+            None => 0,
+        };
+
+        Ok(ScanFilters { extensions, exclude, min_size })
+    }
+
+    /// Whether a path should be walked at all. Checked on every directory
+    /// entry before it is recursed into or read, so excluded subtrees are
+    /// skipped without being descended into.
+    pub fn allows_path(&self, path: &Path) -> bool {
+        match &self.exclude {
+            Some(exclude) => !exclude.is_match(path),
+            None => true,
+        }
+    }
+
+    /// Whether a file clears the extension allowlist and minimum size floor.
+    pub fn allows_file(&self, path: &Path, size: u64) -> bool {
+        if size < self.min_size {
+            return false;
+        }
+
+        if let Some(extensions) = &self.extensions {
+            let matches = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| extensions.contains(&ext.to_lowercase()));
+
+            if !matches {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Parse a human size string like "100MB" into a byte count by inverting
+/// `format_size`'s unit table.
+pub fn parse_size(raw: &str) -> Result<u64, String> {
+    let trimmed = raw.trim();
+    let split_at = trimmed.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split_at);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("Invalid size '{}': expected a number followed by a unit (e.g. 100MB)", raw))?;
+
+    let suffix_index = unit_index(unit).ok_or_else(|| format!("Unknown size unit '{}' in '{}'", unit, raw))?;
+
+    Ok((number * 1024f64.powi(suffix_index as i32)) as u64)
+}
+
+fn unit_index(unit: &str) -> Option<usize> {
+    let normalized = unit.trim();
+    if normalized.is_empty() {
+        return Some(0);
+    }
+
+    UNITS
+        .iter()
+        .position(|candidate| candidate.eq_ignore_ascii_case(normalized))
+        .or_else(|| {
+            // Accept a bare letter like "M" or "K" as shorthand for "MB"/"KB"
+            let with_suffix = format!("{}B", normalized);
+            UNITS.iter().position(|candidate| candidate.eq_ignore_ascii_case(&with_suffix))
+        })
+}
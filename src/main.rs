@@ -1,18 +1,18 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::env;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use clap::Parser;
-use walkdir::WalkDir;
-use tabled::{
-    settings::{
-        object::{Columns}, Alignment, Style,
-    },
-    Tabled,
-    Table
-};
+use rayon::prelude::*;
+
+mod filters;
+mod output;
+use filters::ScanFilters;
+use output::OutputFormat;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -24,72 +24,143 @@ struct Args {
     /// Number of files to display, default is 100
     #[arg(short, long, default_value_t = 100)]
     count: usize,
+
+    /// Number of worker threads for the parallel walk, default is available parallelism
+    #[arg(short, long)]
+    threads: Option<usize>,
+
+    /// Rank by on-disk (allocated) size instead of apparent size, accounting
+    /// for sparse files and block padding
+    #[arg(short, long)]
+    disk_usage: bool,
+
+    /// Count every hard-linked path separately instead of deduplicating by
+    /// inode (the default skips paths whose (dev, ino) was already seen)
+    #[arg(long)]
+    count_hard_links: bool,
+
+    /// Rank directories by recursive subtree size instead of ranking
+    /// individual files
+    #[arg(short = 'D', long)]
+    dirs: bool,
+
+    /// Output format for the results
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    /// Only consider these comma-separated extensions (e.g. png,jpg)
+    #[arg(long, value_name = "EXT,EXT,...")]
+    ext: Option<String>,
+
+    /// Glob pattern to exclude from the scan (e.g. "**/node_modules/**"); repeatable
+    #[arg(long)]
+    exclude: Vec<String>,
+
+    /// Only consider files at or above this size (e.g. 100MB)
+    #[arg(long, value_name = "SIZE")]
+    min_size: Option<String>,
+}
+
+/// Tracks which `(dev, ino)` pairs have already been counted so hard-linked
+/// paths sharing one inode's blocks aren't reported as separate "fat" files.
+/// Shared across walker threads behind a mutex; each lookup is a single
+/// short `HashSet::insert` next to the metadata syscalls surrounding it.
+struct InodeFilter {
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl InodeFilter {
+    fn new() -> Self {
+        InodeFilter {
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Returns `true` the first time this inode is seen, `false` on every
+    /// later hard link to the same data. Non-Unix platforms have no stable
+    /// inode identity here, so every path degrades to "unique".
+    #[cfg(unix)]
+    fn first_sighting(&self, metadata: &std::fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        let key = (metadata.dev(), metadata.ino());
+        self.seen.lock().unwrap().insert(key)
+    }
+
+    #[cfg(not(unix))]
+    fn first_sighting(&self, _metadata: &std::fs::Metadata) -> bool {
+        true
+    }
 }
 
 struct FileData {
     path: String,
     size: u64,
+    allocated: u64,
 }
 
 impl FileData {
-    fn new(path: String, size: u64) -> FileData {
-        FileData { path, size }
+    fn new(path: String, size: u64, allocated: u64) -> FileData {
+        FileData { path, size, allocated }
+    }
+
+    /// The size the current ranking mode cares about: on-disk when
+    /// `disk_usage` is set, apparent (`metadata.len()`) otherwise.
+    fn effective_size(&self, disk_usage: bool) -> u64 {
+        if disk_usage { self.allocated } else { self.size }
     }
 }
 
 struct FileCollection {
     files: Vec<FileData>,
     max_size: usize,
+    disk_usage: bool,
 }
 
 impl FileCollection {
-    fn new(max_size: usize) -> Self {
+    fn new(max_size: usize, disk_usage: bool) -> Self {
         FileCollection {
             files: Vec::new(),
             max_size,
+            disk_usage,
         }
     }
 
     fn smart_insert(&mut self, file: FileData) {
+        let disk_usage = self.disk_usage;
         if self.files.len() < self.max_size {
             self.files.push(file);
             if self.files.len() == self.max_size {
                 // Sort once at full capacity
-                self.files.sort_by(|a, b| b.size.cmp(&a.size));
+                self.files.sort_by(|a, b| b.effective_size(disk_usage).cmp(&a.effective_size(disk_usage)));
             }
         } else {
             // Perform binary search and insert if collection is full
-            if let Some(index) = self.find_insert_position(&file.size) {
+            if let Some(index) = self.find_insert_position(file.effective_size(disk_usage)) {
                 self.files.insert(index, file);
                 self.files.pop(); // Remove the smallest file to maintain size limit
             }
         }
     }
 
-    fn find_insert_position(&self, target_size: &u64) -> Option<usize> {
+    fn find_insert_position(&self, target_size: u64) -> Option<usize> {
+        let disk_usage = self.disk_usage;
         // Return None if the size is smaller than the smallest file
-        if self.files.is_empty() || *target_size < self.files[self.files.len() - 1].size {
+        if self.files.is_empty() || target_size < self.files[self.files.len() - 1].effective_size(disk_usage) {
             return None;
         }
 
         // Use binary search for efficiency
-        match self.files.binary_search_by(|file| file.size.cmp(target_size).reverse()) {
+        match self.files.binary_search_by(|file| file.effective_size(disk_usage).cmp(&target_size).reverse()) {
             Ok(pos) | Err(pos) => Some(pos),
         }
     }
-}
-
-#[derive(Tabled)]
-struct FileDataTable {
-    #[tabled(rename = "Path")]
-    path: String,
-    #[tabled(rename = "Size")]
-    size: String
-}
 
-impl FileDataTable {
-    fn new(path: String, size: String) -> FileDataTable {
-        FileDataTable { path, size }
+    /// Fold another thread's bounded collection into this one, keeping only
+    /// the `max_size` largest files overall.
+    fn merge(&mut self, other: FileCollection) {
+        for file in other.files {
+            self.smart_insert(file);
+        }
     }
 }
 
@@ -115,6 +186,169 @@ fn format_size(bytes: usize, with_decimals: bool) -> String {
     format!("{} {}", size_str, UNITS[suffix_index])
 }
 
+/// Resolve both the apparent (`metadata.len()`) and on-disk (allocated)
+/// size for a file. Allocated size is only computed in disk-usage mode (it
+/// is 0 otherwise); if `filesize::file_real_size_fast` fails, the apparent
+/// size is used in its place.
+fn resolve_sizes(path: &Path, metadata: &std::fs::Metadata, disk_usage: bool) -> (u64, u64) {
+    let apparent = metadata.len();
+    let allocated = if disk_usage {
+        filesize::file_real_size_fast(path, metadata).unwrap_or(apparent)
+    } else {
+        0
+    };
+    (apparent, allocated)
+}
+
+/// Walk `dir`'s entries, recursing into subdirectories as rayon tasks so
+/// parallelism isn't limited to the root's immediate children (a tree with
+/// one top-level directory and thousands of nested subdirectories still
+/// fans out across every level, not just depth 1). Each task keeps its own
+/// bounded `FileCollection`, merged back up the recursion via `reduce`.
+///
+/// `entry.file_type()` (an `lstat`, like `WalkDir`'s default) decides dir vs.
+/// file so symlinks are skipped rather than followed — otherwise a symlink
+/// back to an ancestor recurses forever, and a symlink to a file gets double
+/// counted alongside its target.
+fn walk_parallel(dir: &Path, count: usize, disk_usage: bool, inode_filter: Option<&InodeFilter>, filters: &ScanFilters) -> FileCollection {
+    let entries: Vec<(PathBuf, std::fs::FileType)> = match std::fs::read_dir(dir) {
+        Ok(read_dir) => read_dir
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                if !filters.allows_path(&path) {
+                    return None;
+                }
+                entry.file_type().ok().map(|file_type| (path, file_type))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+
+    entries
+        .into_par_iter()
+        .map(|(path, file_type)| {
+            if file_type.is_dir() {
+                walk_parallel(&path, count, disk_usage, inode_filter, filters)
+            } else if file_type.is_file() {
+                let mut collection = FileCollection::new(count, disk_usage);
+                if let Ok(metadata) = path.metadata() {
+                    let (size, allocated) = resolve_sizes(&path, &metadata, disk_usage);
+                    if size > 0 && filters.allows_file(&path, size) {
+                        // Only claim this inode once a candidate has actually
+                        // cleared the scan filters, so a rejected hard link
+                        // doesn't shadow a sibling path to the same data.
+                        let passes = inode_filter.is_none_or(|filter| filter.first_sighting(&metadata));
+                        if passes {
+                            collection.smart_insert(FileData::new(path.to_string_lossy().to_string(), size, allocated));
+                        }
+                    }
+                }
+                collection
+            } else {
+                // Symlinks and other non-regular entries are skipped, not followed.
+                FileCollection::new(count, disk_usage)
+            }
+        })
+        .reduce(
+            || FileCollection::new(count, disk_usage),
+            |mut a, b| {
+                a.merge(b);
+                a
+            },
+        )
+}
+
+/// Fan the walk out across `threads` workers and reduce their bounded
+/// `FileCollection`s into a single top-N collection. `inode_filter`, when
+/// set, is shared across every worker so a hard link discovered by one
+/// thread is still recognized by another.
+fn collect_biggest_files(root: &Path, count: usize, threads: usize, disk_usage: bool, inode_filter: Option<Arc<InodeFilter>>, filters: &ScanFilters) -> FileCollection {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("Failed to build thread pool");
+
+    pool.install(|| walk_parallel(root, count, disk_usage, inode_filter.as_deref(), filters))
+}
+
+/// Sum one directory's direct files plus its already-computed children
+/// (each directory is visited exactly once by this recursion, so no
+/// separate memoization is needed), then feed the subtree total into
+/// `collection` to track the fattest directories. Returns the `(apparent,
+/// allocated)` totals for `dir` itself.
+fn aggregate_dir_size(
+    dir: &Path,
+    disk_usage: bool,
+    inode_filter: Option<&InodeFilter>,
+    filters: &ScanFilters,
+    collection: &mut FileCollection,
+) -> (u64, u64) {
+    let mut apparent_total = 0u64;
+    let mut allocated_total = 0u64;
+
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return (0, 0),
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        if !filters.allows_path(&path) {
+            continue;
+        }
+
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+
+        if file_type.is_dir() {
+            let (child_apparent, child_allocated) = aggregate_dir_size(&path, disk_usage, inode_filter, filters, collection);
+            apparent_total += child_apparent;
+            allocated_total += child_allocated;
+        } else if file_type.is_file() {
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            let (size, allocated) = resolve_sizes(&path, &metadata, disk_usage);
+
+            if !filters.allows_file(&path, size) {
+                continue;
+            }
+
+            // Only claim this inode once a candidate has actually cleared
+            // the scan filters, so a rejected hard link doesn't shadow a
+            // sibling path to the same data that would otherwise have passed.
+            if let Some(filter) = inode_filter {
+                if !filter.first_sighting(&metadata) {
+                    continue;
+                }
+            }
+
+            apparent_total += size;
+            allocated_total += allocated;
+        }
+    }
+
+    collection.smart_insert(FileData::new(dir.to_string_lossy().to_string(), apparent_total, allocated_total));
+
+    (apparent_total, allocated_total)
+}
+
+/// Rank directories by recursive subtree size rather than ranking individual
+/// files. Single-threaded: the bottom-up aggregation needs a child's total
+/// before its parent's, so unlike `collect_biggest_files` there's no
+/// independent top-level work to fan out.
+fn collect_biggest_dirs(root: &Path, count: usize, disk_usage: bool, inode_filter: Option<Arc<InodeFilter>>, filters: &ScanFilters) -> FileCollection {
+    let mut collection = FileCollection::new(count, disk_usage);
+    aggregate_dir_size(root, disk_usage, inode_filter.as_deref(), filters, &mut collection);
+    collection
+}
+
 fn main() {
     let args = Args::parse();
 
@@ -141,69 +375,39 @@ fn main() {
         return;
     }
 
-    let runtime_start = Instant::now();
-    let mut files: Vec<FileData> = Vec::new();
-    for entry in WalkDir::new(&path_str) {
-        if let Ok(entry) = entry {
-            if !entry.file_type().is_file() {
-                continue;
-            }
+    let threads = args.threads.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    });
 
-            let metadata = if let Ok(metadata) = entry.metadata() {
-                metadata
-            } else {
-                continue;
-            };
-
-            let len = metadata.len();
-
-            if len == 0 {
-                continue;
-            }
+    let inode_filter = if args.count_hard_links {
+        None
+    } else {
+        Some(Arc::new(InodeFilter::new()))
+    };
 
-            let file_data = FileData::new(entry.path().to_string_lossy().to_string(), len);
-            files.push(file_data);
+    let filters = match ScanFilters::new(args.ext.as_deref(), &args.exclude, args.min_size.as_deref()) {
+        Ok(filters) => filters,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            return;
         }
-    }
-
-    // Get memory usage of the vec files
-    #[cfg(debug_assertions)]
-    {
-        let vec_size = size_of_val(&files); // Size of the Vec structure
-        let elements_size: usize = files.iter().map(|file| size_of::<FileData>() + file.path.len()).sum(); // Size of all FileData instances
-        let total_memory = vec_size + elements_size; // Total memory usage
-        println!("- Debug Information -");
-        println!("Path: {}", path_str);
-        println!("Memory used by files: {}", format_size(total_memory, false));
-        println!("Number of files: {}", files.len());
-        println!("---------------------")
-    }
+    };
 
-    let mut biggest_files = FileCollection::new(args.count);
-    for file in files {
-        biggest_files.smart_insert(file);
-    }
+    let runtime_start = Instant::now();
+    let biggest_files = if args.dirs {
+        collect_biggest_dirs(path, args.count, args.disk_usage, inode_filter, &filters)
+    } else {
+        collect_biggest_files(path, args.count, threads, args.disk_usage, inode_filter, &filters)
+    };
 
-    let tabled_files: Vec<FileDataTable> = biggest_files
-        .files
-        .into_iter() // Use into_iter to consume the vector and move ownership
-        .map(|file_data| {
-            FileDataTable::new(
-                file_data.path,
-                format_size(file_data.size as usize, true)
-            )
-        })
-        .collect();
     let runtime_end = runtime_start.elapsed();
 
-    let table = Table::new(&tabled_files)
-        .with(Style::psql())
-        .modify(Columns::first(), Alignment::left())
-        .modify(Columns::last(), Alignment::right())
-        .to_string();
+    let rendered = output::render(biggest_files.files, args.output, args.disk_usage);
+    println!("{}", rendered);
 
-    println!("{}", table);
-
-    let end_message = format!("Found the fattest {} files in {:.2}s", args.count, runtime_end.as_secs_f64());
-    println!("{}", end_message);
-}
\ No newline at end of file
+    // Keep stdout clean for piped machine-readable output; the summary is
+    // just for a human watching the terminal.
+    let noun = if args.dirs { "directories" } else { "files" };
+    let end_message = format!("Found the fattest {} {} in {:.2}s", args.count, noun, runtime_end.as_secs_f64());
+    eprintln!("{}", end_message);
+}
@@ -0,0 +1,134 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use tabled::{
+    settings::{
+        object::{Columns}, Alignment, Style,
+    },
+    Tabled,
+    Table,
+};
+
+use crate::{format_size, FileData};
+
+/// How to render the ranked results: the default human table, or one of a
+/// few machine-readable formats for piping into other programs.
+#[derive(ValueEnum, Copy, Clone, Debug)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+#[derive(Tabled)]
+struct FileDataTable {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Size")]
+    size: String
+}
+
+#[derive(Tabled)]
+struct FileDataDiskUsageTable {
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Size (on disk)")]
+    allocated: String,
+    #[tabled(rename = "Apparent size")]
+    size: String,
+}
+
+/// One result row, byte-exact size alongside the human-formatted string so
+/// downstream consumers don't have to parse "1.23 GB" back into a number.
+#[derive(Serialize)]
+struct FileRecord {
+    path: String,
+    size_bytes: u64,
+    size_human: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allocated_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allocated_human: Option<String>,
+}
+
+impl FileRecord {
+    fn from_file_data(file: &FileData, disk_usage: bool) -> FileRecord {
+        FileRecord {
+            path: file.path.clone(),
+            size_bytes: file.size,
+            size_human: format_size(file.size as usize, true),
+            allocated_bytes: disk_usage.then_some(file.allocated),
+            allocated_human: disk_usage.then(|| format_size(file.allocated as usize, true)),
+        }
+    }
+}
+
+/// Render the ranked files in the requested format.
+pub fn render(files: Vec<FileData>, format: OutputFormat, disk_usage: bool) -> String {
+    match format {
+        OutputFormat::Table => render_table(files, disk_usage),
+        OutputFormat::Json => render_json(&files, disk_usage),
+        OutputFormat::Ndjson => render_ndjson(&files, disk_usage),
+        OutputFormat::Csv => render_csv(&files, disk_usage),
+    }
+}
+
+fn render_table(files: Vec<FileData>, disk_usage: bool) -> String {
+    if disk_usage {
+        let rows: Vec<FileDataDiskUsageTable> = files
+            .into_iter()
+            .map(|file| FileDataDiskUsageTable {
+                path: file.path,
+                allocated: format_size(file.allocated as usize, true),
+                size: format_size(file.size as usize, true),
+            })
+            .collect();
+
+        Table::new(&rows)
+            .with(Style::psql())
+            .modify(Columns::first(), Alignment::left())
+            .modify(Columns::last(), Alignment::right())
+            .to_string()
+    } else {
+        let rows: Vec<FileDataTable> = files
+            .into_iter()
+            .map(|file| FileDataTable {
+                path: file.path,
+                size: format_size(file.size as usize, true),
+            })
+            .collect();
+
+        Table::new(&rows)
+            .with(Style::psql())
+            .modify(Columns::first(), Alignment::left())
+            .modify(Columns::last(), Alignment::right())
+            .to_string()
+    }
+}
+
+fn render_json(files: &[FileData], disk_usage: bool) -> String {
+    let records: Vec<FileRecord> = files.iter().map(|file| FileRecord::from_file_data(file, disk_usage)).collect();
+    serde_json::to_string_pretty(&records).expect("Failed to serialize results as JSON")
+}
+
+fn render_ndjson(files: &[FileData], disk_usage: bool) -> String {
+    files
+        .iter()
+        .map(|file| {
+            let record = FileRecord::from_file_data(file, disk_usage);
+            serde_json::to_string(&record).expect("Failed to serialize result as JSON")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn render_csv(files: &[FileData], disk_usage: bool) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for file in files {
+        writer
+            .serialize(FileRecord::from_file_data(file, disk_usage))
+            .expect("Failed to serialize result as CSV");
+    }
+    let bytes = writer.into_inner().expect("Failed to flush CSV writer");
+    String::from_utf8(bytes).expect("CSV output was not valid UTF-8")
+}